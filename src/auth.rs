@@ -0,0 +1,77 @@
+//! Session-based authentication guarding the mutating book routes.
+//!
+//! Reads stay public; `add_book`/`update_book`/`delete_book` require a
+//! bearer session token obtained from [`login`]. Sessions are tracked
+//! in-process, which is sufficient for this single-instance service.
+
+use crate::db::verify_credentials;
+use axum::extract::{Extension, Request};
+use axum::http::{header, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Json;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgPool;
+use std::collections::HashSet;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Active session tokens, issued by [`login`] and consumed by [`require_session`].
+static SESSIONS: Lazy<RwLock<HashSet<Uuid>>> = Lazy::new(|| RwLock::new(HashSet::new()));
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: Uuid,
+}
+
+/// Verify credentials and, on success, issue a session token that the
+/// caller must send as `Authorization: Bearer <token>` on writes.
+pub async fn login(
+    Extension(connection_pool): Extension<PgPool>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, StatusCode> {
+    let valid = verify_credentials(&connection_pool, &request.username, &request.password)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if !valid {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let token = Uuid::new_v4();
+    SESSIONS.write().await.insert(token);
+    Ok(Json(LoginResponse { token }))
+}
+
+/// `true` for methods that mutate state and therefore require a session.
+fn mutates(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Middleware that rejects mutating requests (`POST`/`PUT`/`DELETE`/...)
+/// unless they carry a bearer token from an active session. Reads pass
+/// through untouched.
+pub async fn require_session(request: Request, next: Next) -> Result<Response, StatusCode> {
+    if !mutates(request.method()) {
+        return Ok(next.run(request).await);
+    }
+
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| Uuid::parse_str(token).ok());
+
+    match token {
+        Some(token) if SESSIONS.read().await.contains(&token) => Ok(next.run(request).await),
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
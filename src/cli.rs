@@ -0,0 +1,32 @@
+//! Command-line entry points for the service: serving requests, and the
+//! one-off schema/data management tasks that used to happen implicitly on
+//! every `main` invocation.
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(name = "webinar_axumcrud", about = "Book catalog service")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (create the database and migrate first).
+    Serve,
+    /// Create the configured database if it doesn't already exist.
+    CreateDb,
+    /// Run any pending sqlx migrations against the configured database.
+    Migrate,
+    /// Insert a starter set of books into the database.
+    Seed,
+    /// Create a user that can log in and obtain a session via `/login`.
+    CreateUser {
+        username: String,
+        password: String,
+    },
+    /// Print the configured database's `postgres://` connection string, for
+    /// feeding into `psql` or another external tool.
+    PrintConnectionString,
+}
@@ -0,0 +1,224 @@
+//! Typed, layered configuration for the service.
+//!
+//! Settings are assembled from `config/default.toml`, an optional
+//! environment-specific file (`config/local.toml` or
+//! `config/production.toml`, selected via `APP_ENVIRONMENT`), and finally
+//! environment-variable overrides prefixed `APP` (e.g. `APP__DATABASE__PORT`).
+//! Later sources win, so environment variables always have the final say.
+
+use serde::Deserialize;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
+use std::time::Duration;
+
+/// Top-level application configuration.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    pub application: ApplicationSettings,
+    pub database: DatabaseSettings,
+    pub seed: SeedSettings,
+}
+
+/// Settings for the HTTP server itself.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApplicationSettings {
+    /// The address to bind the listener to (e.g. `0.0.0.0`).
+    pub host: String,
+    #[serde(deserialize_with = "deserialize_port")]
+    pub port: u16,
+    /// The host clients actually reach the service on, used to build
+    /// absolute URLs. Usually different from `host` (which is a bind
+    /// address, not necessarily routable).
+    pub public_host: String,
+}
+
+/// Settings for the Postgres connection.
+#[derive(Clone, Deserialize)]
+pub struct DatabaseSettings {
+    pub host: String,
+    #[serde(deserialize_with = "deserialize_port")]
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub database_name: String,
+    pub ssl_mode: SslMode,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+    /// When true, the connection suppresses sqlx's per-statement query
+    /// logging (useful for tests and high-throughput deployments).
+    pub disable_logging: bool,
+    /// How long a cached book (or the full book list) stays valid before
+    /// being re-fetched from Postgres, even without a local write.
+    pub cache_ttl_secs: u64,
+}
+
+/// Redacts `password` so a stray `tracing::debug!("{:?}", settings)` or
+/// `anyhow` error context can't leak it to logs.
+impl std::fmt::Debug for DatabaseSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DatabaseSettings")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &"***REDACTED***")
+            .field("database_name", &self.database_name)
+            .field("ssl_mode", &self.ssl_mode)
+            .field("max_connections", &self.max_connections)
+            .field("acquire_timeout_secs", &self.acquire_timeout_secs)
+            .field("disable_logging", &self.disable_logging)
+            .field("cache_ttl_secs", &self.cache_ttl_secs)
+            .finish()
+    }
+}
+
+/// The set of books the `seed` CLI subcommand inserts, so it can be varied
+/// per environment instead of being baked into the binary.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SeedSettings {
+    pub starter_books: Vec<StarterBook>,
+}
+
+/// A single book to insert when seeding, as configured in `[[seed.starter_books]]`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StarterBook {
+    pub title: String,
+    pub author: String,
+}
+
+/// Which TLS negotiation mode to use when talking to Postgres, mirroring
+/// `sqlx::postgres::PgSslMode`'s non-exotic variants.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SslMode {
+    Disable,
+    Prefer,
+    Require,
+}
+
+impl From<SslMode> for PgSslMode {
+    fn from(mode: SslMode) -> Self {
+        match mode {
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+        }
+    }
+}
+
+impl DatabaseSettings {
+    /// Connection options for the server, without selecting a database.
+    /// Used to create the target database if it doesn't exist yet.
+    pub fn connect_options_without_db(&self) -> PgConnectOptions {
+        PgConnectOptions::new()
+            .host(&self.host)
+            .port(self.port)
+            .username(&self.username)
+            .password(&self.password)
+            .ssl_mode(self.ssl_mode.into())
+    }
+
+    /// Connection options for the server, with the target database selected.
+    pub fn connect_options_with_db(&self) -> PgConnectOptions {
+        self.connect_options_without_db()
+            .database(&self.database_name)
+    }
+
+    /// A `postgres://` connection string built from the same fields, for
+    /// tooling that wants a URL rather than `PgConnectOptions` (e.g. `psql`
+    /// or another ops tool invoked via the `print-connection-string` CLI
+    /// subcommand).
+    pub fn connection_string(&self) -> String {
+        format!(
+            "postgres://{}:{}@{}:{}/{}",
+            self.username, self.password, self.host, self.port, self.database_name
+        )
+    }
+
+    /// The pool sizing/timeout knobs shared by every connection built from
+    /// these settings.
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+    }
+}
+
+/// Accepts a port given either as an integer or a numeric string, since
+/// environment-variable overrides always arrive as strings.
+fn deserialize_port<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum PortOrString {
+        Port(u16),
+        Text(String),
+    }
+
+    match PortOrString::deserialize(deserializer)? {
+        PortOrString::Port(port) => Ok(port),
+        PortOrString::Text(text) => text
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("`{}` is not a valid port", text))),
+    }
+}
+
+/// Which environment-specific config file to layer on top of the defaults.
+pub enum Environment {
+    Local,
+    Production,
+}
+
+impl Environment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Local => "local",
+            Environment::Production => "production",
+        }
+    }
+}
+
+impl TryFrom<String> for Environment {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "production" => Ok(Self::Production),
+            other => Err(format!(
+                "`{}` is not a supported environment. Use `local` or `production`.",
+                other
+            )),
+        }
+    }
+}
+
+/// Load `Settings` from `config/default.toml`, layered with the
+/// environment-specific file and `APP`-prefixed environment variables.
+pub fn get_configuration() -> Result<Settings, config::ConfigError> {
+    let base_path = std::env::current_dir().expect("Failed to determine the current directory");
+    let configuration_directory = base_path.join("config");
+
+    let environment: Environment = std::env::var("APP_ENVIRONMENT")
+        .unwrap_or_else(|_| "local".into())
+        .try_into()
+        .map_err(config::ConfigError::Message)?;
+    let environment_filename = format!("{}.toml", environment.as_str());
+
+    let settings = config::Config::builder()
+        .add_source(config::File::from(
+            configuration_directory.join("default.toml"),
+        ))
+        .add_source(
+            config::File::from(configuration_directory.join(environment_filename))
+                .required(false),
+        )
+        .add_source(
+            config::Environment::with_prefix("APP")
+                .prefix_separator("_")
+                .separator("__"),
+        )
+        .build()?;
+
+    settings.try_deserialize::<Settings>()
+}
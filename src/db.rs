@@ -3,49 +3,177 @@
 //! The database is assumed to be in-memory, and rebuilt from
 //! scratch on each start-up.
 
-use anyhow::Result;
+use crate::config::{DatabaseSettings, StarterBook};
+use anyhow::{anyhow, Result};
+use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, Row, postgres::PgPool};
-use tokio::sync::RwLock;
+use log::LevelFilter;
+use sqlx::{ConnectOptions, FromRow, postgres::PgPool};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
-use std::env;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// How to obtain the connection pool used by the service: build a fresh one
+/// from configuration, or reuse one a caller already has (e.g. a test
+/// harness that wants to inject its own pool without re-reading config).
+pub enum ConnectionOptions {
+    Fresh {
+        pool_options: PgPoolOptions,
+        disable_logging: bool,
+        url: Box<PgConnectOptions>,
+    },
+    /// Only ever constructed by tests that want to inject their own pool
+    /// without re-reading configuration.
+    #[cfg(test)]
+    Existing(PgPool),
+}
+
+impl ConnectionOptions {
+    /// Resolve these options into a ready-to-use connection pool.
+    pub async fn connect(self) -> Result<PgPool> {
+        match self {
+            ConnectionOptions::Fresh {
+                pool_options,
+                disable_logging,
+                url,
+            } => {
+                let mut url = *url;
+                if disable_logging {
+                    url = url
+                        .log_statements(LevelFilter::Off)
+                        .log_slow_statements(LevelFilter::Off, Duration::from_secs(0));
+                }
+                Ok(pool_options.connect_with(url).await?)
+            }
+            #[cfg(test)]
+            ConnectionOptions::Existing(pool) => Ok(pool),
+        }
+    }
+}
 
 /// Represents a book, taken from the books table in SQLite.
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
 pub struct Book {
-    /// The book's primary key ID
-    pub id: i32,
+    /// The book's primary key ID, generated application-side
+    pub id: Uuid,
     /// The book's title
     pub title: String,
     /// The book's author (surname, lastname - not enforced)
     pub author: String,
 }
 
+/// How long a cached entry stays valid without a local write before it's
+/// treated as stale and re-fetched from Postgres. Defaults to 30s; set by
+/// `init_db` from `DatabaseSettings::cache_ttl_secs`.
+static CACHE_TTL_SECS: AtomicU64 = AtomicU64::new(30);
+
+fn cache_ttl() -> Duration {
+    Duration::from_secs(CACHE_TTL_SECS.load(Ordering::Relaxed))
+}
+
+fn set_cache_ttl(ttl_secs: u64) {
+    CACHE_TTL_SECS.store(ttl_secs, Ordering::Relaxed);
+}
+
+/// A cached value alongside when it was cached, so it can be expired by TTL.
+struct Cached<T> {
+    value: T,
+    cached_at: Instant,
+}
+
+impl<T> Cached<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            cached_at: Instant::now(),
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.cached_at.elapsed() < cache_ttl()
+    }
+}
+
+/// Caches both the full book list and individual books by id, so single-book
+/// reads don't have to hit Postgres just because the full list wasn't asked
+/// for. Writes patch the affected entry in place rather than dropping
+/// everything cached.
 struct BookCache {
-    all_books: RwLock<Option<Vec<Book>>>,
+    all_books: RwLock<Option<Cached<Vec<Book>>>>,
+    by_id: RwLock<HashMap<Uuid, Cached<Book>>>,
 }
 
 impl BookCache {
     fn new() -> Self {
         Self {
             all_books: RwLock::new(None),
+            by_id: RwLock::new(HashMap::new()),
         }
     }
 
     async fn all_books(&self) -> Option<Vec<Book>> {
         let lock = self.all_books.read().await;
-        lock.clone()
+        lock.as_ref()
+            .filter(|cached| cached.is_fresh())
+            .map(|cached| cached.value.clone())
     }
 
-    async fn refresh(&self, books: Vec<Book>) {
+    async fn book_by_id(&self, id: Uuid) -> Option<Book> {
+        let lock = self.by_id.read().await;
+        lock.get(&id)
+            .filter(|cached| cached.is_fresh())
+            .map(|cached| cached.value.clone())
+    }
+
+    /// Replace the cached full list, and (re)populate the by-id index from it.
+    async fn refresh_all(&self, books: Vec<Book>) {
+        let mut by_id = self.by_id.write().await;
+        for book in &books {
+            by_id.insert(book.id, Cached::new(book.clone()));
+        }
+        drop(by_id);
+
         let mut lock = self.all_books.write().await;
-        *lock = Some(books);
+        *lock = Some(Cached::new(books));
     }
 
-    async fn invalidate(&self) {
+    /// Record a book that was just added or updated, patching it into the
+    /// cached list in place instead of invalidating the whole thing.
+    async fn upsert(&self, book: Book) {
+        self.by_id
+            .write()
+            .await
+            .insert(book.id, Cached::new(book.clone()));
+
+        let mut lock = self.all_books.write().await;
+        if let Some(cached) = lock.as_mut() {
+            match cached.value.iter_mut().find(|b| b.id == book.id) {
+                Some(existing) => *existing = book,
+                None => cached.value.push(book),
+            }
+            // Keep the cached list in the same order `all_books`'s query
+            // returns, since a patched-in book is otherwise appended to the
+            // end regardless of its title.
+            cached
+                .value
+                .sort_by(|a, b| (&a.title, &a.author, a.id).cmp(&(&b.title, &b.author, b.id)));
+            cached.cached_at = Instant::now();
+        }
+    }
+
+    /// Remove a deleted book from both the by-id index and the cached list.
+    async fn remove(&self, id: Uuid) {
+        self.by_id.write().await.remove(&id);
+
         let mut lock = self.all_books.write().await;
-        *lock = None;
+        if let Some(cached) = lock.as_mut() {
+            cached.value.retain(|b| b.id != id);
+        }
     }
 }
 
@@ -53,41 +181,34 @@ static CACHE: Lazy<BookCache> = Lazy::new(BookCache::new);
 
 // todo: to seperate the create the database if not exist to seperate file
 /// Check if the database exists and create it if it doesn't
-pub async fn create_db_if_not_exists() -> Result<()> {
-    let host = env::var("POSTGRES_HOST").unwrap_or("127.0.0.1".to_string());
-    let port: u16 = env::var("POSTGRES_PORT").unwrap_or("5432".to_string()).parse().unwrap();
-    let user = env::var("POSTGRES_USER").unwrap_or("postgres".to_string());
-    let password = env::var("POSTGRES_PASSWORD").unwrap_or("postgres".to_string());
-    let db_name = env::var("POSTGRES_DB").unwrap_or("rust_sqlx".to_string());
-
-    // Create connection options for connecting to the Postgres server without a specific database
-    let connect_options = PgConnectOptions::new()
-        .host(&host)
-        .port(port)
-        .username(&user)
-        .password(&password)
-        .database("postgres"); // Connect to the default 'postgres' database
-
-    // Create a connection pool for the general Postgres connection
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect_with(connect_options)
-        .await?;
+///
+/// ## Arguments
+/// * `settings` - the typed database settings to connect with
+pub async fn create_db_if_not_exists(settings: &DatabaseSettings) -> Result<()> {
+    // Connect to the server without selecting the target database, since it
+    // may not exist yet.
+    let pool = ConnectionOptions::Fresh {
+        pool_options: settings.pool_options(),
+        disable_logging: settings.disable_logging,
+        url: Box::new(settings.connect_options_without_db()),
+    }
+    .connect()
+    .await?;
 
     // Check if the database exists, and if not, create it
     let db_exists: (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM pg_database WHERE datname = $1)")
-        .bind(&db_name)
+        .bind(&settings.database_name)
         .fetch_one(&pool)
         .await?;
 
     if !db_exists.0 {
         // If the database does not exist, create it
-        sqlx::query(&format!("CREATE DATABASE {}", db_name))
+        sqlx::query(&format!("CREATE DATABASE {}", settings.database_name))
             .execute(&pool)
             .await?;
-        println!("Database '{}' created successfully!", db_name);
+        println!("Database '{}' created successfully!", settings.database_name);
     } else {
-        println!("Database '{}' already exists.", db_name);
+        println!("Database '{}' already exists.", settings.database_name);
     }
 
     Ok(())
@@ -95,15 +216,25 @@ pub async fn create_db_if_not_exists() -> Result<()> {
 
 /// Create a database connection pool. Run any migrations.
 ///
+/// ## Arguments
+/// * `settings` - the typed database settings to connect with
+///
 /// ## Returns
 /// * A ready-to-use connection pool.
-pub async fn init_db() -> Result<PgPool> {
-    let database_url = env::var("DATABASE_URL")?;
-    let connection_pool = PgPool::connect(&database_url).await?;
+pub async fn init_db(settings: &DatabaseSettings) -> Result<PgPool> {
+    let connection_pool = ConnectionOptions::Fresh {
+        pool_options: settings.pool_options(),
+        disable_logging: settings.disable_logging,
+        url: Box::new(settings.connect_options_with_db()),
+    }
+    .connect()
+    .await?;
 
     // Run migrations
     sqlx::migrate!().run(&connection_pool).await?;
 
+    set_cache_ttl(settings.cache_ttl_secs);
+
     Ok(connection_pool)
 }
 
@@ -118,10 +249,10 @@ pub async fn all_books(connection_pool: &PgPool) -> Result<Vec<Book>> {
     if let Some(all_books) = CACHE.all_books().await {
         Ok(all_books)
     } else {
-        let books = sqlx::query_as::<_, Book>("SELECT * FROM books ORDER BY id, title,author")
+        let books = sqlx::query_as::<_, Book>("SELECT * FROM books ORDER BY title, author, id")
             .fetch_all(connection_pool)
             .await?;
-        CACHE.refresh(books.clone()).await;
+        CACHE.refresh_all(books.clone()).await;
         Ok(books)
     }
 }
@@ -131,14 +262,21 @@ pub async fn all_books(connection_pool: &PgPool) -> Result<Vec<Book>> {
 /// ## Arguments
 /// * `connection_pool` - the database connection pool to use
 /// * `id` - the primary key of the book to retrieve
-pub async fn book_by_id(connection_pool: &PgPool, id: i32) -> Result<Book> {
-    Ok(sqlx::query_as::<_, Book>("SELECT * FROM books WHERE id=$1")
+pub async fn book_by_id(connection_pool: &PgPool, id: Uuid) -> Result<Book> {
+    if let Some(book) = CACHE.book_by_id(id).await {
+        return Ok(book);
+    }
+
+    let book = sqlx::query_as::<_, Book>("SELECT * FROM books WHERE id=$1")
         .bind(id)
         .fetch_one(connection_pool)
-        .await?)
+        .await?;
+    CACHE.upsert(book.clone()).await;
+    Ok(book)
 }
 
-/// Adds a book to the database.
+/// Adds a book to the database. The primary key is generated here, in
+/// Rust, so callers know the book's id without a round-trip to Postgres.
 ///
 /// ## Arguments
 /// * `connection_pool` - the database connection to use
@@ -151,16 +289,43 @@ pub async fn add_book<S: ToString>(
     connection_pool: &PgPool,
     title: S,
     author: S,
-) -> Result<i32> {
+) -> Result<Uuid> {
+    add_book_with_id(connection_pool, None, title, author).await
+}
+
+/// Adds a book to the database, optionally under a caller-supplied id.
+///
+/// Passing `id` lets the REST layer accept client-supplied ids
+/// idempotently: a retried request with the same id is a no-op rather
+/// than a duplicate row, since the insert is ignored on a conflict.
+/// Passing `None` generates a fresh id, same as [`add_book`].
+///
+/// ## Arguments
+/// * `connection_pool` - the database connection to use
+/// * `id` - the primary key to insert under, or `None` to generate one
+/// * `title` - the title of the book to add
+/// * `author` - the author of the book to add
+///
+/// ## Returns
+/// * The primary key value of the book (the existing one, if `id` already existed)
+pub async fn add_book_with_id<S: ToString>(
+    connection_pool: &PgPool,
+    id: Option<Uuid>,
+    title: S,
+    author: S,
+) -> Result<Uuid> {
     let title = title.to_string();
     let author = author.to_string();
-    let id = sqlx::query("INSERT INTO books (title, author) VALUES ($1, $2) RETURNING id")
-        .bind(title)
-        .bind(author)
-        .fetch_one(connection_pool)
-        .await?
-        .get(0);
-    CACHE.invalidate().await;
+    let id = id.unwrap_or_else(Uuid::new_v4);
+    sqlx::query(
+        "INSERT INTO books (id, title, author) VALUES ($1, $2, $3) ON CONFLICT (id) DO NOTHING",
+    )
+    .bind(id)
+    .bind(&title)
+    .bind(&author)
+    .execute(connection_pool)
+    .await?;
+    CACHE.upsert(Book { id, title, author }).await;
     Ok(id)
 }
 
@@ -169,15 +334,15 @@ pub async fn add_book<S: ToString>(
 /// ## Arguments
 /// * `connection_pool` - the database connection to use
 /// * `book` - the book object to update. The primary key will be used to
-///            determine which row is updated.
+///   determine which row is updated.
 pub async fn update_book(connection_pool: &PgPool, book: &Book) -> Result<()> {
     sqlx::query("UPDATE books SET title=$1, author=$2 WHERE id=$3")
         .bind(&book.title)
         .bind(&book.author)
-        .bind(&book.id)
+        .bind(book.id)
         .execute(connection_pool)
         .await?;
-    CACHE.invalidate().await;
+    CACHE.upsert(book.clone()).await;
     Ok(())
 }
 
@@ -186,43 +351,177 @@ pub async fn update_book(connection_pool: &PgPool, book: &Book) -> Result<()> {
 /// ## Arguments
 /// * `connection_pool` - the database connection to use
 /// * `id` - the primary key of the book to delete
-pub async fn delete_book(connection_pool: &PgPool, id: i32) -> Result<()> {
+pub async fn delete_book(connection_pool: &PgPool, id: Uuid) -> Result<()> {
     sqlx::query("DELETE FROM books WHERE id=$1")
         .bind(id)
         .execute(connection_pool)
         .await?;
-    CACHE.invalidate().await;
+    CACHE.remove(id).await;
     Ok(())
 }
 
+/// Insert the configured set of starter books.
+///
+/// ## Arguments
+/// * `connection_pool` - the database connection to use
+/// * `starter_books` - the books to insert, from `SeedSettings`
+pub async fn seed_books(connection_pool: &PgPool, starter_books: &[StarterBook]) -> Result<()> {
+    for book in starter_books {
+        add_book(connection_pool, book.title.clone(), book.author.clone()).await?;
+    }
+    Ok(())
+}
+
+/// Represents a user account, taken from the users table. Not queried back
+/// anywhere yet (`create_user`/`verify_credentials` only write/check the
+/// hash), but kept as the `FromRow` shape a future user-lookup endpoint
+/// would use.
+#[allow(dead_code)]
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct User {
+    pub id: Uuid,
+    pub username: String,
+    #[serde(skip_serializing)]
+    password_hash: String,
+}
+
+/// Create a user account, hashing the password with Argon2 before it is
+/// ever written to the database.
+///
+/// ## Arguments
+/// * `connection_pool` - the database connection to use
+/// * `username` - the account's username, must be unique
+/// * `password` - the plaintext password to hash and store
+///
+/// ## Returns
+/// * The primary key of the new user
+pub async fn create_user(connection_pool: &PgPool, username: &str, password: &str) -> Result<Uuid> {
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow!("Failed to hash password: {e}"))?
+        .to_string();
+
+    let id = Uuid::new_v4();
+    sqlx::query("INSERT INTO users (id, username, password_hash) VALUES ($1, $2, $3)")
+        .bind(id)
+        .bind(username)
+        .bind(password_hash)
+        .execute(connection_pool)
+        .await?;
+    Ok(id)
+}
+
+/// A fixed Argon2 hash with no corresponding known password, verified
+/// against when a username doesn't exist so that an unknown username
+/// takes the same amount of time to reject as a wrong password for a
+/// known one.
+static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| {
+    Argon2::default()
+        .hash_password(b"not a real password", &SaltString::generate(&mut OsRng))
+        .expect("hashing a fixed dummy password cannot fail")
+        .to_string()
+});
+
+/// Verify a username/password pair against the stored Argon2 hash.
+///
+/// Always runs an Argon2 verification, even when the username doesn't
+/// exist, so that the response time doesn't leak whether the username
+/// is registered.
+///
+/// ## Arguments
+/// * `connection_pool` - the database connection to use
+/// * `username` - the username to look up
+/// * `password` - the plaintext password to check
+///
+/// ## Returns
+/// * `true` if the credentials match a known user, `false` otherwise
+///   (including when the username doesn't exist at all, so callers can't
+///   distinguish "wrong password" from "no such user").
+pub async fn verify_credentials(
+    connection_pool: &PgPool,
+    username: &str,
+    password: &str,
+) -> Result<bool> {
+    let row: Option<(String,)> =
+        sqlx::query_as("SELECT password_hash FROM users WHERE username=$1")
+            .bind(username)
+            .fetch_optional(connection_pool)
+            .await?;
+
+    let user_exists = row.is_some();
+    let password_hash = row.map_or_else(|| DUMMY_PASSWORD_HASH.clone(), |(hash,)| hash);
+
+    let parsed_hash =
+        PasswordHash::new(&password_hash).map_err(|e| anyhow!("Stored password hash is invalid: {e}"))?;
+    let password_matches = Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok();
+
+    Ok(user_exists && password_matches)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::config::get_configuration;
+
+    /// Load the typed settings used by the test database, honoring the same
+    /// `config/*.toml` + env var layering as the running service.
+    fn test_settings() -> DatabaseSettings {
+        dotenv::dotenv().ok();
+        get_configuration()
+            .expect("Failed to read configuration")
+            .database
+    }
 
     #[sqlx::test]
     async fn get_all() {
-        dotenv::dotenv().ok();
-        let cnn = init_db().await.unwrap();
+        let cnn = init_db(&test_settings()).await.unwrap();
+        add_book(&cnn, "Hands-on Rust", "Wolverson, Herbert")
+            .await
+            .unwrap();
         let all_rows = all_books(&cnn).await.unwrap();
         assert!(!all_rows.is_empty());
     }
 
+    #[sqlx::test]
+    async fn all_books_sorts_by_title_then_author() {
+        let cnn = init_db(&test_settings()).await.unwrap();
+        let suffix = Uuid::new_v4();
+        let title_c = format!("{suffix}-C Book");
+        let title_a = format!("{suffix}-A Book");
+        let title_b = format!("{suffix}-B Book");
+        add_book(&cnn, title_c.as_str(), "Author").await.unwrap();
+        add_book(&cnn, title_a.as_str(), "Author").await.unwrap();
+        add_book(&cnn, title_b.as_str(), "Author").await.unwrap();
+
+        let titles: Vec<String> = all_books(&cnn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|book| book.title)
+            .filter(|title| title.contains(&suffix.to_string()))
+            .collect();
+        assert_eq!(vec![title_a, title_b, title_c], titles);
+    }
+
     #[sqlx::test]
     async fn get_one() {
-        dotenv::dotenv().ok();
-        let cnn = init_db().await.unwrap();
-        let book = book_by_id(&cnn, 1).await.unwrap();
-        assert_eq!(1, book.id);
+        let cnn = init_db(&test_settings()).await.unwrap();
+        let id = add_book(&cnn, "Hands-on Rust", "Wolverson, Herbert")
+            .await
+            .unwrap();
+        let book = book_by_id(&cnn, id).await.unwrap();
+        assert_eq!(id, book.id);
         assert_eq!("Hands-on Rust", book.title);
         assert_eq!("Wolverson, Herbert", book.author);
     }
 
     #[sqlx::test]
     async fn test_create() {
-        dotenv::dotenv().ok();
-
         // Only initialize the pool and run migrations
-        let pool = init_db().await.unwrap();
+        let pool = init_db(&test_settings()).await.unwrap();
 
         let new_id = add_book(&pool, "Test Book", "Test Author").await.unwrap();
         let new_book = book_by_id(&pool, new_id).await.unwrap();
@@ -234,23 +533,119 @@ mod test {
 
     #[sqlx::test]
     async fn test_update() {
-        dotenv::dotenv().ok();
-        let cnn = init_db().await.unwrap();
-        let mut book = book_by_id(&cnn, 2).await.unwrap();
+        let cnn = init_db(&test_settings()).await.unwrap();
+        let id = add_book(&cnn, "Original Book", "Test Author")
+            .await
+            .unwrap();
+        let mut book = book_by_id(&cnn, id).await.unwrap();
         book.title = "Updated Book".to_string();
         update_book(&cnn, &book).await.unwrap();
-        let updated_book = book_by_id(&cnn, 2).await.unwrap();
+        let updated_book = book_by_id(&cnn, id).await.unwrap();
         assert_eq!("Updated Book", updated_book.title);
     }
 
     #[sqlx::test]
     async fn test_delete() {
-        dotenv::dotenv().ok();
-        let cnn = init_db().await.unwrap();
+        let cnn = init_db(&test_settings()).await.unwrap();
         let new_id = add_book(&cnn, "DeleteMe", "Test Author").await.unwrap();
         let _new_book = book_by_id(&cnn, new_id).await.unwrap();
         delete_book(&cnn, new_id).await.unwrap();
         let all_books = all_books(&cnn).await.unwrap();
         assert!(all_books.iter().find(|b| b.title == "DeleteMe").is_none());
     }
+
+    #[sqlx::test]
+    async fn create_user_does_not_store_plaintext_password() {
+        let cnn = init_db(&test_settings()).await.unwrap();
+        let username = format!("alice-{}", Uuid::new_v4());
+        create_user(&cnn, &username, "correct horse battery staple")
+            .await
+            .unwrap();
+
+        let (stored_hash,): (String,) =
+            sqlx::query_as("SELECT password_hash FROM users WHERE username=$1")
+                .bind(&username)
+                .fetch_one(&cnn)
+                .await
+                .unwrap();
+        assert_ne!("correct horse battery staple", stored_hash);
+    }
+
+    #[sqlx::test]
+    async fn verify_credentials_rejects_wrong_password() {
+        let cnn = init_db(&test_settings()).await.unwrap();
+        let username = format!("bob-{}", Uuid::new_v4());
+        create_user(&cnn, &username, "correct horse battery staple")
+            .await
+            .unwrap();
+
+        assert!(
+            verify_credentials(&cnn, &username, "correct horse battery staple")
+                .await
+                .unwrap()
+        );
+        assert!(!verify_credentials(&cnn, &username, "wrong password")
+            .await
+            .unwrap());
+        assert!(!verify_credentials(&cnn, "nobody", "anything")
+            .await
+            .unwrap());
+    }
+
+    #[sqlx::test]
+    async fn add_then_read_is_visible_immediately() {
+        let cnn = init_db(&test_settings()).await.unwrap();
+        // Prime the all_books cache before the write.
+        all_books(&cnn).await.unwrap();
+
+        let id = add_book(&cnn, "Fresh Book", "Fresh Author").await.unwrap();
+
+        assert_eq!("Fresh Book", book_by_id(&cnn, id).await.unwrap().title);
+        let all = all_books(&cnn).await.unwrap();
+        assert!(all.iter().any(|b| b.id == id));
+    }
+
+    #[sqlx::test]
+    async fn update_then_read_is_visible_immediately() {
+        let cnn = init_db(&test_settings()).await.unwrap();
+        let id = add_book(&cnn, "Stale Title", "Test Author").await.unwrap();
+        // Prime both caches before the write.
+        all_books(&cnn).await.unwrap();
+        book_by_id(&cnn, id).await.unwrap();
+
+        let mut book = book_by_id(&cnn, id).await.unwrap();
+        book.title = "Fresh Title".to_string();
+        update_book(&cnn, &book).await.unwrap();
+
+        assert_eq!("Fresh Title", book_by_id(&cnn, id).await.unwrap().title);
+        let all = all_books(&cnn).await.unwrap();
+        assert_eq!("Fresh Title", all.iter().find(|b| b.id == id).unwrap().title);
+    }
+
+    #[sqlx::test]
+    async fn delete_then_list_excludes_it() {
+        let cnn = init_db(&test_settings()).await.unwrap();
+        let id = add_book(&cnn, "Gone Soon", "Test Author").await.unwrap();
+        // Prime the list cache before deleting.
+        all_books(&cnn).await.unwrap();
+
+        delete_book(&cnn, id).await.unwrap();
+
+        let all = all_books(&cnn).await.unwrap();
+        assert!(all.iter().all(|b| b.id != id));
+    }
+
+    #[sqlx::test]
+    async fn connection_options_existing_reuses_the_given_pool() {
+        let cnn = init_db(&test_settings()).await.unwrap();
+        let reused = ConnectionOptions::Existing(cnn.clone())
+            .connect()
+            .await
+            .unwrap();
+
+        let id = add_book(&reused, "Via Existing Pool", "Test Author")
+            .await
+            .unwrap();
+        assert_eq!("Via Existing Pool", book_by_id(&cnn, id).await.unwrap().title);
+    }
 }
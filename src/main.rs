@@ -1,66 +1,152 @@
+mod auth;
+mod cli;
+mod config;
 mod db;
 mod rest;
+mod telemetry;
 mod view;
-use crate::db::{init_db, create_db_if_not_exists};
+use crate::cli::{Cli, Command};
+use crate::config::{get_configuration, Settings};
+use crate::db::{create_db_if_not_exists, create_user, init_db, seed_books};
+use crate::telemetry::{get_subscriber, init_subscriber};
 use anyhow::Result;
-use axum::{Extension, Router};
+use axum::extract::{ConnectInfo, MatchedPath};
+use axum::http::Request;
+use axum::routing::post;
+use axum::{middleware, Extension, Router};
+use clap::Parser;
 use sqlx::postgres::PgPool;
-use std::env;
 use std::net::SocketAddr;
-use tracing::{error, info};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt}; // Arc is needed to share Mutex across async tasks
+use tower_http::trace::TraceLayer;
+use tracing::{error, info, info_span};
+use uuid::Uuid;
+
+/// Shared, request-independent server state made available to handlers via
+/// `Extension<ServerState>` (e.g. so the view layer can build absolute URLs
+/// from the host clients actually reach the service on).
+#[derive(Clone)]
+pub(crate) struct ServerState {
+    pub(crate) public_host: String,
+}
 
 /// Build the overall web service router.
 /// Constructing the router in a function makes it easy to re-use in unit tests.
-fn router(connection_pool: PgPool) -> Router {
+fn router(connection_pool: PgPool, state: ServerState) -> Router {
     Router::new()
         // Nest service allows you to attach another router to a URL base.
         // "/" inside the service will be "/books" to the outside world.
-        .nest_service("/books", rest::books_service())
+        // Mutating requests (POST/PUT/DELETE) require a logged-in session;
+        // reads stay public.
+        .nest_service(
+            "/books",
+            rest::books_service().layer(middleware::from_fn(auth::require_session)),
+        )
         // Add the web view
         .nest_service("/", view::view_service())
-        // Add the connection pool as a "layer", available for dependency injection.
+        // Issue a session token for a valid username/password.
+        .route("/login", post(auth::login))
+        // Add the connection pool and server state as "layers", available
+        // for dependency injection.
         .layer(Extension(connection_pool))
+        .layer(Extension(state))
+        // Open a span per request, carrying a request id, method, the
+        // matched route, and the client's address, so every db call inside
+        // a handler logs within it.
+        .layer(TraceLayer::new_for_http().make_span_with(|request: &Request<_>| {
+            let matched_path = request
+                .extensions()
+                .get::<MatchedPath>()
+                .map(MatchedPath::as_str);
+            let client_ip = request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.to_string());
+
+            info_span!(
+                "request",
+                request_id = %Uuid::new_v4(),
+                method = %request.method(),
+                matched_path,
+                client_ip,
+            )
+        }))
 }
 
-//todo: seperate the function of creating the db
+/// Run the selected subcommand to completion.
+async fn run(command: Command, configuration: Settings) -> Result<()> {
+    match command {
+        Command::CreateDb => create_db_if_not_exists(&configuration.database).await,
+        Command::Migrate => {
+            init_db(&configuration.database).await?;
+            Ok(())
+        }
+        Command::Seed => {
+            create_db_if_not_exists(&configuration.database).await?;
+            let pool = init_db(&configuration.database).await?;
+            seed_books(&pool, &configuration.seed.starter_books).await
+        }
+        Command::CreateUser { username, password } => {
+            create_db_if_not_exists(&configuration.database).await?;
+            let pool = init_db(&configuration.database).await?;
+            create_user(&pool, &username, &password).await?;
+            Ok(())
+        }
+        Command::PrintConnectionString => {
+            println!("{}", configuration.database.connection_string());
+            Ok(())
+        }
+        Command::Serve => {
+            create_db_if_not_exists(&configuration.database).await?;
+
+            // Initialize the database and obtain a connection pool
+            let connection_pool = init_db(&configuration.database).await?;
+
+            let state = ServerState {
+                public_host: configuration.application.public_host.clone(),
+            };
+
+            // Initialize the Axum routing service
+            let app = router(connection_pool, state);
+
+            // Define the address to listen on (everything)
+            let addr: SocketAddr = format!(
+                "{}:{}",
+                configuration.application.host, configuration.application.port
+            )
+            .parse()
+            .expect("Unable to parse socket address");
 
+            info!("Server running on http://{}", addr);
+
+            let listner = tokio::net::TcpListener::bind(addr).await?;
+            axum::serve(
+                listner,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await?;
+
+            Ok(())
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load environment variables from .env if available
     dotenv::dotenv().ok();
 
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-     // Ensure the database exists
-     create_db_if_not_exists().await?;
-
-    // Initialize the database and obtain a connection pool
-    let connection_pool = init_db().await?;
-
-    // Initialize the Axum routing service
-    let app = router(connection_pool);
-    
-    
-    let ip = env::var("ip").expect("IP address not set in .env");
-    let port = env::var("port").expect("Port not set in .env");
-    
-    // Define the address to listen on (everything)
-    let addr: SocketAddr = format!("{}:{}", ip, port)
-    .parse()
-    .expect("Unable to parse socket address");
-
-    info!("Server running on http://{}", addr);
-
-    let listner = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listner, app).await.unwrap_or_else(|e| {
-        error!("Server error: {}", e);
+    let subscriber = get_subscriber("webinar_axumcrud".into(), "info".into(), std::io::stdout);
+    init_subscriber(subscriber);
+
+    let cli = Cli::parse();
+
+    // Load typed configuration from config/*.toml, layered with env vars
+    let configuration = get_configuration().expect("Failed to read configuration");
+
+    if let Err(err) = run(cli.command, configuration).await {
+        error!("{}", err);
         std::process::exit(1);
-    });
+    }
 
     Ok(())
-    
 }
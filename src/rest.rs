@@ -0,0 +1,77 @@
+//! JSON REST API for book resources, nested under `/books` by `main`.
+
+use crate::db::{self, Book};
+use axum::extract::{Extension, Path};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use sqlx::postgres::PgPool;
+use uuid::Uuid;
+
+/// Build the `/books` REST service: list/create at `/`, read/update/delete
+/// at `/:id`.
+pub fn books_service() -> Router {
+    Router::new()
+        .route("/", get(list_books).post(create_book))
+        .route("/:id", get(get_book).put(update_book).delete(remove_book))
+}
+
+async fn list_books(Extension(pool): Extension<PgPool>) -> Result<Json<Vec<Book>>, StatusCode> {
+    db::all_books(&pool)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn get_book(
+    Extension(pool): Extension<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Book>, StatusCode> {
+    db::book_by_id(&pool, id)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::NOT_FOUND)
+}
+
+#[derive(Debug, Deserialize)]
+struct NewBook {
+    /// A client-supplied id, so a retried request is idempotent instead of
+    /// creating a duplicate book. Omit to have the server generate one.
+    #[serde(default)]
+    id: Option<Uuid>,
+    title: String,
+    author: String,
+}
+
+async fn create_book(
+    Extension(pool): Extension<PgPool>,
+    Json(new_book): Json<NewBook>,
+) -> Result<Json<Uuid>, StatusCode> {
+    db::add_book_with_id(&pool, new_book.id, new_book.title, new_book.author)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn update_book(
+    Extension(pool): Extension<PgPool>,
+    Path(id): Path<Uuid>,
+    Json(mut book): Json<Book>,
+) -> Result<StatusCode, StatusCode> {
+    book.id = id;
+    db::update_book(&pool, &book)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn remove_book(
+    Extension(pool): Extension<PgPool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, StatusCode> {
+    db::delete_book(&pool, id)
+        .await
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
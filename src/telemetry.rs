@@ -0,0 +1,41 @@
+//! Structured, JSON-formatted tracing setup for the service.
+//!
+//! Builds a subscriber that combines an `EnvFilter` (honoring `RUST_LOG`), a
+//! bunyan-style JSON formatting layer with nested span context, and a bridge
+//! that forwards `log`-crate records into `tracing`. Exposed as
+//! `get_subscriber`/`init_subscriber` so integration tests can install the
+//! exact same setup as the running service.
+
+use tracing::Subscriber;
+use tracing::subscriber::set_global_default;
+use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
+use tracing_log::LogTracer;
+use tracing_subscriber::{EnvFilter, Registry, layer::SubscriberExt};
+
+/// Build a subscriber that emits bunyan-style JSON records, without
+/// installing it globally. `env_filter` is the default filter directive
+/// used when `RUST_LOG` isn't set.
+pub fn get_subscriber<Sink>(
+    name: String,
+    env_filter: String,
+    sink: Sink,
+) -> impl Subscriber + Send + Sync
+where
+    Sink: for<'a> tracing_subscriber::fmt::MakeWriter<'a> + Send + Sync + 'static,
+{
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
+    let formatting_layer = BunyanFormattingLayer::new(name, sink);
+    Registry::default()
+        .with(env_filter)
+        .with(JsonStorageLayer)
+        .with(formatting_layer)
+}
+
+/// Install the given subscriber as the global default, bridging `log`
+/// records into `tracing` first so dependencies that still use `log` are
+/// captured with the same structure.
+pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync) {
+    LogTracer::init().expect("Failed to set logger");
+    set_global_default(subscriber).expect("Failed to set subscriber");
+}
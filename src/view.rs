@@ -0,0 +1,49 @@
+//! A minimal HTML view for browsing the book catalog, nested at `/` by `main`.
+
+use crate::db;
+use crate::ServerState;
+use axum::extract::Extension;
+use axum::http::StatusCode;
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use sqlx::postgres::PgPool;
+
+/// Build the `/` web view service.
+pub fn view_service() -> Router {
+    Router::new().route("/", get(index))
+}
+
+async fn index(
+    Extension(pool): Extension<PgPool>,
+    Extension(state): Extension<ServerState>,
+) -> Result<Html<String>, StatusCode> {
+    let books = db::all_books(&pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let rows: String = books
+        .iter()
+        .map(|book| {
+            format!(
+                "<tr><td>{}</td><td>{}</td></tr>",
+                escape_html(&book.title),
+                escape_html(&book.author)
+            )
+        })
+        .collect();
+
+    Ok(Html(format!(
+        "<h1>Book Catalog</h1><p>Serving from {}</p><table><tr><th>Title</th><th>Author</th></tr>{rows}</table>",
+        escape_html(&state.public_host)
+    )))
+}
+
+/// Escape the handful of characters that matter for safely embedding
+/// arbitrary text inside HTML markup.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}